@@ -1,12 +1,20 @@
 // rust_processor/src/lib.rs
 use byteorder::{BigEndian, ReadBytesExt};
 use chrono::{DateTime, NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::Cursor;
 use std::net::Ipv4Addr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+mod telemetry;
+mod websocket;
 
 #[pyclass]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetflowHeader {
     #[pyo3(get)]
     pub version: u16,
@@ -41,7 +49,7 @@ impl NetflowHeader {
 }
 
 #[pyclass]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowRecord {
     #[pyo3(get)]
     pub srcaddr: String,
@@ -85,8 +93,35 @@ pub struct FlowRecord {
     pub pad2: u8,
 }
 
+impl Default for FlowRecord {
+    fn default() -> Self {
+        Self {
+            srcaddr: "0.0.0.0".to_string(),
+            dstaddr: "0.0.0.0".to_string(),
+            nexthop: "0.0.0.0".to_string(),
+            input_snmp: 0,
+            output_snmp: 0,
+            packets: 0,
+            bytes: 0,
+            first: 0,
+            last: 0,
+            srcport: 0,
+            dstport: 0,
+            pad1: 0,
+            tcp_flags: 0,
+            protocol: 0,
+            tos: 0,
+            src_as: 0,
+            dst_as: 0,
+            src_mask: 0,
+            dst_mask: 0,
+            pad2: 0,
+        }
+    }
+}
+
 #[pyclass]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetflowPacket {
     #[pyo3(get)]
     pub header: NetflowHeader,
@@ -109,9 +144,19 @@ impl NetflowPacket {
         ))
     }
 
+    /// Human-readable name of the exporter protocol this packet was decoded as.
+    fn version_name(&self) -> PyResult<String> {
+        Ok(match self.header.version {
+            5 => "NetFlow v5".to_string(),
+            9 => "NetFlow v9".to_string(),
+            10 => "IPFIX".to_string(),
+            other => format!("Unknown({})", other),
+        })
+    }
+
     fn print_packet_info(&self) -> PyResult<String> {
         let mut output = String::new();
-        
+
         output.push_str(&format!("\n{}\n", "=".repeat(70)));
         output.push_str(&format!(
             "Netflow Packet #{} received from {}:{}\n",
@@ -119,8 +164,10 @@ impl NetflowPacket {
         ));
         output.push_str(&format!(" Timestamp: {}\n", self.header.timestamp));
         output.push_str(&format!(
-            "Version: {}, Flow count: {}\n",
-            self.header.version, self.header.count
+            "Version: {} ({}), Flow count: {}\n",
+            self.header.version,
+            self.version_name()?,
+            self.header.count
         ));
         output.push_str(&format!(" Sequence: {}\n", self.header.flow_sequence));
         output.push_str(&format!("  System uptime: {} ms\n", self.header.sys_uptime));
@@ -142,11 +189,291 @@ impl NetflowPacket {
     }
 }
 
+// -- NetFlow v9 / IPFIX template handling ------------------------------------------------------
+//
+// Unlike v5's fixed 48-byte record, v9 and IPFIX exporters describe their record layout with
+// templates that are sent (and re-sent periodically) as their own FlowSets. Data FlowSets only
+// carry a template id, so the field layout has to be remembered across packets - templates
+// routinely outlive the packet they arrived in, and data frequently shows up again before the
+// next template refresh. The cache is keyed by exporter + source/observation-domain id + template
+// id, since two exporters (or two observation domains behind one exporter) may reuse the same id
+// for unrelated layouts.
+
+const TEMPLATE_FLOWSET_ID_V9: u16 = 0;
+const TEMPLATE_SET_ID_IPFIX: u16 = 2;
+
+const FIELD_IN_BYTES: u16 = 1;
+const FIELD_IN_PKTS: u16 = 2;
+const FIELD_PROTOCOL: u16 = 4;
+const FIELD_L4_SRC_PORT: u16 = 7;
+const FIELD_IPV4_SRC_ADDR: u16 = 8;
+const FIELD_L4_DST_PORT: u16 = 11;
+const FIELD_IPV4_DST_ADDR: u16 = 12;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct TemplateKey {
+    source_addr: String,
+    source_id: u32,
+    template_id: u16,
+}
+
+#[derive(Debug, Clone)]
+struct TemplateField {
+    field_type: u16,
+    field_length: u16,
+}
+
+#[derive(Debug, Clone)]
+struct Template {
+    fields: Vec<TemplateField>,
+}
+
+// A long-running receiver behind NAT/load-balanced exporters (source_addr churn) or exporters
+// that re-template often would otherwise grow this map forever. Entries are dropped once they
+// haven't been touched (learned or matched against a Data FlowSet) for TEMPLATE_CACHE_TTL, and
+// the map is capped at TEMPLATE_CACHE_MAX_ENTRIES by evicting the least-recently-touched entries
+// first, so a single misbehaving exporter can't exhaust memory on its own.
+const TEMPLATE_CACHE_TTL: Duration = Duration::from_secs(3600);
+const TEMPLATE_CACHE_MAX_ENTRIES: usize = 4096;
+
+struct CachedTemplate {
+    template: Template,
+    last_seen: Instant,
+}
+
+static TEMPLATE_CACHE: Lazy<Mutex<HashMap<TemplateKey, CachedTemplate>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn evict_stale_templates(cache: &mut HashMap<TemplateKey, CachedTemplate>) {
+    let now = Instant::now();
+    cache.retain(|_, cached| now.duration_since(cached.last_seen) < TEMPLATE_CACHE_TTL);
+
+    if cache.len() > TEMPLATE_CACHE_MAX_ENTRIES {
+        let mut by_age: Vec<(TemplateKey, Instant)> =
+            cache.iter().map(|(k, v)| (k.clone(), v.last_seen)).collect();
+        by_age.sort_by_key(|(_, last_seen)| *last_seen);
+
+        let overflow = cache.len() - TEMPLATE_CACHE_MAX_ENTRIES;
+        for (key, _) in by_age.into_iter().take(overflow) {
+            cache.remove(&key);
+        }
+    }
+}
+
+fn read_uint_be(bytes: &[u8]) -> u32 {
+    let mut value: u32 = 0;
+    for b in bytes.iter().take(4) {
+        value = (value << 8) | (*b as u32);
+    }
+    value
+}
+
+fn read_ipv4_field(bytes: &[u8]) -> String {
+    if bytes.len() >= 4 {
+        Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()
+    } else {
+        "0.0.0.0".to_string()
+    }
+}
+
+fn register_templates(set_body: &[u8], source_addr: &str, source_id: u32) {
+    let mut cursor = Cursor::new(set_body);
+
+    while (cursor.position() as usize) + 4 <= set_body.len() {
+        let template_id = match cursor.read_u16::<BigEndian>() {
+            Ok(v) => v,
+            Err(_) => break,
+        };
+        let field_count = match cursor.read_u16::<BigEndian>() {
+            Ok(v) => v,
+            Err(_) => break,
+        };
+
+        let mut fields = Vec::with_capacity(field_count as usize);
+        let mut truncated = false;
+        for _ in 0..field_count {
+            let field_type = match cursor.read_u16::<BigEndian>() {
+                Ok(v) => v,
+                Err(_) => {
+                    truncated = true;
+                    break;
+                }
+            };
+            let field_length = match cursor.read_u16::<BigEndian>() {
+                Ok(v) => v,
+                Err(_) => {
+                    truncated = true;
+                    break;
+                }
+            };
+            fields.push(TemplateField { field_type, field_length });
+        }
+        if truncated {
+            break;
+        }
+
+        let mut cache = TEMPLATE_CACHE.lock().unwrap();
+        cache.insert(
+            TemplateKey {
+                source_addr: source_addr.to_string(),
+                source_id,
+                template_id,
+            },
+            CachedTemplate {
+                template: Template { fields },
+                last_seen: Instant::now(),
+            },
+        );
+        evict_stale_templates(&mut cache);
+    }
+}
+
+fn decode_data_record(data: &[u8], template: &Template) -> FlowRecord {
+    let mut flow = FlowRecord::default();
+    let mut offset = 0usize;
+
+    for field in &template.fields {
+        let len = field.field_length as usize;
+        if offset + len > data.len() {
+            break;
+        }
+        let field_bytes = &data[offset..offset + len];
+
+        match field.field_type {
+            FIELD_IN_BYTES => flow.bytes = read_uint_be(field_bytes),
+            FIELD_IN_PKTS => flow.packets = read_uint_be(field_bytes),
+            FIELD_PROTOCOL => flow.protocol = field_bytes.first().copied().unwrap_or(0),
+            FIELD_L4_SRC_PORT => flow.srcport = read_uint_be(field_bytes) as u16,
+            FIELD_IPV4_SRC_ADDR => flow.srcaddr = read_ipv4_field(field_bytes),
+            FIELD_L4_DST_PORT => flow.dstport = read_uint_be(field_bytes) as u16,
+            FIELD_IPV4_DST_ADDR => flow.dstaddr = read_ipv4_field(field_bytes),
+            _ => {}
+        }
+
+        offset += len;
+    }
+
+    flow
+}
+
+/// Walks the FlowSets in a v9/IPFIX packet body, learning templates as they appear and decoding
+/// any Data FlowSet whose template is already known. `template_set_id` distinguishes the two
+/// wire formats (0 for v9, 2 for IPFIX); everything else (option templates, data for a template
+/// we haven't seen yet) is skipped rather than treated as an error, since exporters are free to
+/// interleave them and templates legitimately lag their data on the wire.
+fn process_flowsets(body: &[u8], template_set_id: u16, source_addr: &str, source_id: u32) -> Vec<FlowRecord> {
+    let mut flows = Vec::new();
+    let mut cursor = Cursor::new(body);
+
+    while (cursor.position() as usize) + 4 <= body.len() {
+        let set_id = match cursor.read_u16::<BigEndian>() {
+            Ok(v) => v,
+            Err(_) => break,
+        };
+        let set_length = match cursor.read_u16::<BigEndian>() {
+            Ok(v) => v,
+            Err(_) => break,
+        };
+        if set_length < 4 {
+            break;
+        }
+
+        let set_start = cursor.position() as usize;
+        if set_start > body.len() {
+            break;
+        }
+        let set_end = std::cmp::min(set_start + (set_length as usize - 4), body.len());
+        let set_body = &body[set_start..set_end];
+
+        if set_id == template_set_id {
+            register_templates(set_body, source_addr, source_id);
+        } else if set_id >= 256 {
+            let key = TemplateKey {
+                source_addr: source_addr.to_string(),
+                source_id,
+                template_id: set_id,
+            };
+            let template = TEMPLATE_CACHE.lock().unwrap().get_mut(&key).map(|cached| {
+                cached.last_seen = Instant::now();
+                cached.template.clone()
+            });
+
+            if let Some(template) = template {
+                let record_len: usize = template.fields.iter().map(|f| f.field_length as usize).sum();
+                if record_len > 0 {
+                    let mut offset = 0usize;
+                    while offset + record_len <= set_body.len() {
+                        flows.push(decode_data_record(&set_body[offset..offset + record_len], &template));
+                        offset += record_len;
+                    }
+                }
+            }
+            // Unknown template: the data record is silently dropped until the exporter re-sends
+            // its template, rather than failing the whole packet.
+        }
+
+        cursor.set_position(set_end as u64);
+    }
+
+    flows
+}
+
+fn format_unix_timestamp(unix_secs: u32) -> String {
+    if unix_secs > 0 {
+        let dt = DateTime::<Utc>::from_utc(
+            NaiveDateTime::from_timestamp_opt(unix_secs as i64, 0).unwrap_or_default(),
+            Utc,
+        );
+        dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+    } else {
+        "Invalid timestamp".to_string()
+    }
+}
+
 fn parse_netflow_packet_internal(
     data: &[u8],
     source_addr: &str,
     source_port: u16,
     packet_number: u32,
+) -> PyResult<NetflowPacket> {
+    if data.len() < 2 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "Packet too short for netflow header",
+        ));
+    }
+
+    let version = Cursor::new(&data[0..2])
+        .read_u16::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading version: {}", e)))?;
+
+    let result = telemetry::with_parse_span(version, || match version {
+        5 => parse_v5_packet(data, source_addr, source_port, packet_number),
+        9 => parse_v9_packet(data, source_addr, source_port, packet_number),
+        10 => parse_ipfix_packet(data, source_addr, source_port, packet_number),
+        other => Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "Unsupported netflow version: {}",
+            other
+        ))),
+    });
+
+    match &result {
+        Ok(packet) => {
+            telemetry::record_flows_decoded(packet.flows.len() as u64);
+            for flow in &packet.flows {
+                telemetry::record_protocol_traffic(flow.protocol, flow.bytes as u64, flow.packets as u64);
+            }
+        }
+        Err(_) => telemetry::record_parse_failure(),
+    }
+
+    result
+}
+
+fn parse_v5_packet(
+    data: &[u8],
+    source_addr: &str,
+    source_port: u16,
+    packet_number: u32,
 ) -> PyResult<NetflowPacket> {
     if data.len() < 24 {
         return Err(pyo3::exceptions::PyValueError::new_err(
@@ -186,15 +513,7 @@ fn parse_netflow_packet_internal(
         .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading sampling_interval: {}", e)))?;
 
     // Create timestamp string (simplified without pyo3-chrono)
-    let timestamp = if unix_secs > 0 {
-        let dt = DateTime::<Utc>::from_utc(
-            NaiveDateTime::from_timestamp_opt(unix_secs as i64, 0).unwrap_or_default(),
-            Utc,
-        );
-        dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
-    } else {
-        "Invalid timestamp".to_string()
-    };
+    let timestamp = format_unix_timestamp(unix_secs);
 
     let header = NetflowHeader {
         version,
@@ -239,6 +558,123 @@ fn parse_netflow_packet_internal(
     })
 }
 
+fn parse_v9_packet(
+    data: &[u8],
+    source_addr: &str,
+    source_port: u16,
+    packet_number: u32,
+) -> PyResult<NetflowPacket> {
+    if data.len() < 20 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "Packet too short for netflow v9 header",
+        ));
+    }
+
+    let mut cursor = Cursor::new(data);
+
+    let version = cursor
+        .read_u16::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading version: {}", e)))?;
+    let count = cursor
+        .read_u16::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading count: {}", e)))?;
+    let sys_uptime = cursor
+        .read_u32::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading sys_uptime: {}", e)))?;
+    let unix_secs = cursor
+        .read_u32::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading unix_secs: {}", e)))?;
+    let package_sequence = cursor
+        .read_u32::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading package_sequence: {}", e)))?;
+    let source_id = cursor
+        .read_u32::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading source_id: {}", e)))?;
+
+    let timestamp = format_unix_timestamp(unix_secs);
+
+    let header = NetflowHeader {
+        version,
+        count,
+        sys_uptime,
+        unix_secs,
+        unix_nsecs: 0,
+        flow_sequence: package_sequence,
+        engine_type: 0,
+        engine_id: 0,
+        sampling_interval: 0,
+        timestamp,
+    };
+
+    let flows = process_flowsets(&data[20..], TEMPLATE_FLOWSET_ID_V9, source_addr, source_id);
+
+    Ok(NetflowPacket {
+        header,
+        flows,
+        source_addr: source_addr.to_string(),
+        source_port,
+        packet_number,
+    })
+}
+
+fn parse_ipfix_packet(
+    data: &[u8],
+    source_addr: &str,
+    source_port: u16,
+    packet_number: u32,
+) -> PyResult<NetflowPacket> {
+    if data.len() < 16 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "Packet too short for IPFIX header",
+        ));
+    }
+
+    let mut cursor = Cursor::new(data);
+
+    let version = cursor
+        .read_u16::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading version: {}", e)))?;
+    let message_length = cursor
+        .read_u16::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading length: {}", e)))?;
+    let export_time = cursor
+        .read_u32::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading export_time: {}", e)))?;
+    let sequence_number = cursor
+        .read_u32::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading sequence_number: {}", e)))?;
+    let observation_domain_id = cursor
+        .read_u32::<BigEndian>()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Error reading observation_domain_id: {}", e)))?;
+
+    let timestamp = format_unix_timestamp(export_time);
+
+    let header = NetflowHeader {
+        version,
+        count: 0,
+        sys_uptime: 0,
+        unix_secs: export_time,
+        unix_nsecs: 0,
+        flow_sequence: sequence_number,
+        engine_type: 0,
+        engine_id: 0,
+        sampling_interval: 0,
+        timestamp,
+    };
+
+    let body_end = std::cmp::min(message_length as usize, data.len());
+    let body = if body_end > 16 { &data[16..body_end] } else { &[] };
+    let flows = process_flowsets(body, TEMPLATE_SET_ID_IPFIX, source_addr, observation_domain_id);
+
+    Ok(NetflowPacket {
+        header,
+        flows,
+        source_addr: source_addr.to_string(),
+        source_port,
+        packet_number,
+    })
+}
+
 fn parse_flow_record_internal(data: &[u8]) -> Result<FlowRecord, String> {
     if data.len() < 48 {
     return Err("Flow record too short".to_string());
@@ -362,6 +798,48 @@ fn get_protocol_name(protocol_num: u8) -> PyResult<String> {
     Ok(protocol_name.to_string())
 }
 
+/// Serializes a parsed packet to JSON, so pipelines can ship flows to message brokers or log
+/// sinks without walking the pyo3 getters field-by-field in Python.
+#[pyfunction]
+fn packet_to_json(packet: &NetflowPacket) -> PyResult<String> {
+    serde_json::to_string(packet).map_err(|e| {
+        pyo3::exceptions::PyValueError::new_err(format!("Error serializing packet to JSON: {}", e))
+    })
+}
+
+/// Serializes a parsed packet to MessagePack, which keeps per-flow overhead small at high
+/// packet rates compared to JSON.
+#[pyfunction]
+fn packet_to_msgpack(packet: &NetflowPacket) -> PyResult<Vec<u8>> {
+    rmp_serde::to_vec(packet).map_err(|e| {
+        pyo3::exceptions::PyValueError::new_err(format!("Error serializing packet to MessagePack: {}", e))
+    })
+}
+
+/// Initializes OpenTelemetry export. A no-op unless the crate is built with the `telemetry`
+/// feature.
+#[pyfunction]
+fn init_telemetry() -> PyResult<()> {
+    telemetry::init();
+    Ok(())
+}
+
+/// Starts the WebSocket fan-out server (see the `websocket` module) listening on `addr`, e.g.
+/// `"0.0.0.0:9001"`. Intended to be called once at startup from the Python side.
+#[pyfunction]
+fn start_websocket_feed(addr: String) -> PyResult<()> {
+    websocket::spawn_server(addr);
+    Ok(())
+}
+
+/// Broadcasts a parsed packet as a JSON frame to every connected WebSocket subscriber.
+#[pyfunction]
+fn broadcast_packet(packet: &NetflowPacket) -> PyResult<()> {
+    let frame = packet_to_json(packet)?;
+    websocket::broadcast_json(frame);
+    Ok(())
+}
+
 #[pyfunction]
 fn process_packet_rust(
     data: &[u8],
@@ -378,6 +856,11 @@ fn netflow_processor(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(parse_netflow_packet, m)?)?;
     m.add_function(wrap_pyfunction!(get_protocol_name, m)?)?;
     m.add_function(wrap_pyfunction!(process_packet_rust, m)?)?;
+    m.add_function(wrap_pyfunction!(packet_to_json, m)?)?;
+    m.add_function(wrap_pyfunction!(packet_to_msgpack, m)?)?;
+    m.add_function(wrap_pyfunction!(init_telemetry, m)?)?;
+    m.add_function(wrap_pyfunction!(start_websocket_feed, m)?)?;
+    m.add_function(wrap_pyfunction!(broadcast_packet, m)?)?;
     m.add_class::<NetflowHeader>()?;
     m.add_class::<FlowRecord>()?;
     m.add_class::<NetflowPacket>()?;
@@ -407,3 +890,125 @@ impl FlowRecord {
         Ok(protocol_name.to_string())
     }
 }
+
+// -- v9/IPFIX FlowSet parsing tests ------------------------------------------------------------
+//
+// `process_flowsets`/`register_templates`/`decode_data_record` parse untrusted bytes off the wire
+// and carry mutable state (the template cache) across calls, so these build synthetic FlowSets by
+// hand rather than relying on a real exporter. Each test uses its own source_addr/source_id so
+// they don't interfere with each other through the shared `TEMPLATE_CACHE`.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::WriteBytesExt;
+
+    const TEST_FIELDS: [(u16, u16); 5] = [
+        (FIELD_IPV4_SRC_ADDR, 4),
+        (FIELD_L4_SRC_PORT, 2),
+        (FIELD_IPV4_DST_ADDR, 4),
+        (FIELD_L4_DST_PORT, 2),
+        (FIELD_PROTOCOL, 1),
+    ];
+
+    fn template_record(template_id: u16, fields: &[(u16, u16)]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.write_u16::<BigEndian>(template_id).unwrap();
+        buf.write_u16::<BigEndian>(fields.len() as u16).unwrap();
+        for (field_type, field_length) in fields {
+            buf.write_u16::<BigEndian>(*field_type).unwrap();
+            buf.write_u16::<BigEndian>(*field_length).unwrap();
+        }
+        buf
+    }
+
+    fn flowset(set_id: u16, payload: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.write_u16::<BigEndian>(set_id).unwrap();
+        buf.write_u16::<BigEndian>(4 + payload.len() as u16).unwrap();
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    fn test_data_record() -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&[10, 0, 0, 1]); // IPV4_SRC_ADDR
+        buf.write_u16::<BigEndian>(1234).unwrap(); // L4_SRC_PORT
+        buf.extend_from_slice(&[10, 0, 0, 2]); // IPV4_DST_ADDR
+        buf.write_u16::<BigEndian>(80).unwrap(); // L4_DST_PORT
+        buf.push(6); // PROTOCOL (TCP)
+        buf
+    }
+
+    #[test]
+    fn template_then_data_decodes_flow() {
+        let template_id = 256;
+        let mut body = flowset(TEMPLATE_FLOWSET_ID_V9, &template_record(template_id, &TEST_FIELDS));
+        body.extend(flowset(template_id, &test_data_record()));
+
+        let flows = process_flowsets(&body, TEMPLATE_FLOWSET_ID_V9, "10.1.0.1", 1001);
+
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].srcaddr, "10.0.0.1");
+        assert_eq!(flows[0].srcport, 1234);
+        assert_eq!(flows[0].dstaddr, "10.0.0.2");
+        assert_eq!(flows[0].dstport, 80);
+        assert_eq!(flows[0].protocol, 6);
+    }
+
+    #[test]
+    fn data_before_template_is_dropped_without_panic() {
+        let template_id = 302;
+        let body = flowset(template_id, &test_data_record());
+
+        let flows = process_flowsets(&body, TEMPLATE_FLOWSET_ID_V9, "10.1.0.2", 1002);
+
+        assert!(flows.is_empty());
+    }
+
+    #[test]
+    fn truncated_template_is_ignored_without_panic() {
+        let template_id = 303;
+        let mut record = template_record(template_id, &TEST_FIELDS);
+        record.truncate(record.len() - 3); // cut off mid-field
+        let body = flowset(TEMPLATE_FLOWSET_ID_V9, &record);
+
+        process_flowsets(&body, TEMPLATE_FLOWSET_ID_V9, "10.1.0.3", 1003);
+
+        let cache = TEMPLATE_CACHE.lock().unwrap();
+        assert!(!cache.contains_key(&TemplateKey {
+            source_addr: "10.1.0.3".to_string(),
+            source_id: 1003,
+            template_id,
+        }));
+    }
+
+    #[test]
+    fn truncated_flowset_body_stops_without_panic() {
+        // Declares a set_length far longer than the bytes actually present.
+        let mut body = Vec::new();
+        body.write_u16::<BigEndian>(256).unwrap();
+        body.write_u16::<BigEndian>(9000).unwrap();
+        body.extend_from_slice(&[1, 2, 3, 4]);
+
+        let flows = process_flowsets(&body, TEMPLATE_FLOWSET_ID_V9, "10.1.0.4", 1004);
+
+        assert!(flows.is_empty());
+    }
+
+    #[test]
+    fn eviction_bounds_cache_past_max_entries() {
+        let source_addr = "10.1.0.5";
+        let source_id = 1005;
+        let extra = 100;
+
+        let mut body = Vec::new();
+        for template_id in 0..(TEMPLATE_CACHE_MAX_ENTRIES as u32 + extra) {
+            body.extend(template_record(template_id as u16, &[(FIELD_PROTOCOL, 1)]));
+        }
+
+        register_templates(&body, source_addr, source_id);
+
+        let cache = TEMPLATE_CACHE.lock().unwrap();
+        assert!(cache.len() <= TEMPLATE_CACHE_MAX_ENTRIES);
+    }
+}
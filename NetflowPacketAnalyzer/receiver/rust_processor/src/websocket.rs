@@ -0,0 +1,96 @@
+// rust_processor/src/websocket.rs
+//
+// Streams every decoded NetflowPacket to connected WebSocket subscribers as a JSON frame, so
+// dashboards and other live tooling get a push feed instead of polling Python for new packets.
+// The pyo3 module has no async runtime of its own, so the server and its connection handlers
+// run on a small dedicated tokio runtime kept alive for the life of the process.
+
+use async_tungstenite::tokio::accept_async;
+use async_tungstenite::tungstenite::Message;
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+// Bounded so a slow subscriber can actually be shed: an unbounded channel's send() only fails
+// once the receiver is dropped, so a slow reader would otherwise just pile up an ever-growing
+// backlog in memory instead of being disconnected.
+const SUBSCRIBER_CHANNEL_CAPACITY: usize = 32;
+
+type Subscriber = mpsc::Sender<String>;
+
+// Shared with the telemetry module: both need an entered Tokio runtime to spawn background
+// tasks from a plain synchronous pyfunction, so there is only one runtime for the whole crate
+// rather than each feature starting its own.
+pub(crate) static RUNTIME: Lazy<tokio::runtime::Runtime> = Lazy::new(|| {
+    tokio::runtime::Runtime::new().expect("failed to start websocket runtime")
+});
+
+static SUBSCRIBERS: Lazy<Mutex<Vec<Subscriber>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Starts the WebSocket server in the background. Each call spawns its own listener; a call for
+/// an address that is already bound prints the bind error and that listener task exits.
+pub fn spawn_server(addr: String) {
+    RUNTIME.spawn(async move {
+        let listener = match TcpListener::bind(&addr).await {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("Failed to bind websocket server on {}: {}", addr, e);
+                return;
+            }
+        };
+
+        println!("Websocket flow feed listening on {}", addr);
+
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    RUNTIME.spawn(handle_subscriber(stream, peer.to_string()));
+                }
+                Err(e) => {
+                    eprintln!("Error accepting websocket connection: {}", e);
+                }
+            }
+        }
+    });
+}
+
+async fn handle_subscriber(stream: TcpStream, peer: String) {
+    let ws_stream = match accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            eprintln!("Websocket handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+
+    println!("Websocket subscriber {} connected", peer);
+
+    let (mut sink, mut source) = ws_stream.split();
+    let (tx, mut rx) = mpsc::channel::<String>(SUBSCRIBER_CHANNEL_CAPACITY);
+    SUBSCRIBERS.lock().unwrap().push(tx);
+
+    // This feed is push-only; draining inbound frames just lets us notice the client hung up.
+    let drain = tokio::spawn(async move { while source.next().await.is_some() {} });
+
+    while let Some(frame) = rx.recv().await {
+        if sink.send(Message::Text(frame)).await.is_err() {
+            break;
+        }
+    }
+
+    drain.abort();
+    println!("Websocket subscriber {} disconnected", peer);
+}
+
+/// Pushes a JSON frame to every connected subscriber. `try_send` never blocks: a subscriber whose
+/// channel is closed (client dropped) or full (client reading slower than packets decode) is
+/// dropped from the subscriber list rather than allowed to block the decode path that feeds this
+/// function or accumulate an unbounded backlog in memory.
+pub fn broadcast_json(frame: String) {
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .retain(|tx| tx.try_send(frame.clone()).is_ok());
+}
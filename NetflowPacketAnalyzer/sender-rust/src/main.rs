@@ -5,97 +5,306 @@ use std::time::Duration;
 
 use byteorder::{BigEndian, WriteBytesExt};
 use chrono::Utc;
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::seq::SliceRandom;
 use rand::Rng;
-use tokio::net::{lookup_host, UdpSocket};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::Ipv4Addr;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{lookup_host, TcpStream, UdpSocket};
+
+mod telemetry;
+
+const IPPROTO_SCTP: i32 = 132;
+// Linux errno returned by connect() on a non-blocking socket whose connection is still pending.
+const EINPROGRESS: i32 = 115;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Describes the simulated traffic a single sender generates: which subnets its flows come
+/// from/to, which ports and protocols show up and how often, how many flows get packed into one
+/// PDU, and the NetFlow sampling rate being simulated. `sampling_interval` of N means each
+/// exported record stands in for N real flows, so the reported packet/byte counts are scaled up
+/// accordingly - matching how a real sampled exporter reports "1-in-N" traffic.
+#[derive(Debug, Clone)]
+struct TrafficProfile {
+    src_subnets: Vec<(u8, u8, u8)>,
+    dst_subnets: Vec<(u8, u8, u8)>,
+    dst_ports: Vec<(u16, u32)>,
+    protocols: Vec<(u8, u32)>,
+    flows_per_packet: (u32, u32),
+    sampling_interval: u16,
+}
+
+impl Default for TrafficProfile {
+    fn default() -> Self {
+        Self {
+            src_subnets: vec![(192, 168, 2), (10, 0, 5), (10, 0, 6)],
+            dst_subnets: vec![(10, 0, 1), (172, 16, 3)],
+            dst_ports: vec![(80, 40), (443, 35), (22, 5), (25, 5), (53, 10), (8080, 5)],
+            protocols: vec![(6, 70), (17, 25), (1, 5)],
+            flows_per_packet: (1, 10),
+            sampling_interval: 100,
+        }
+    }
+}
+
+impl TrafficProfile {
+    /// Builds a profile from environment variables, falling back to `default()` field-by-field
+    /// for anything unset or unparseable - same pattern as `ExportTransport::from_env`. Subnets
+    /// are comma-separated `a.b.c` triples (e.g. `"192.168.2,10.0.5"`); weighted lists are
+    /// comma-separated `value:weight` pairs (e.g. `"80:40,443:35,22:5"`).
+    fn from_env() -> Self {
+        let defaults = Self::default();
+
+        Self {
+            src_subnets: parse_subnets_env("SRC_SUBNETS").unwrap_or(defaults.src_subnets),
+            dst_subnets: parse_subnets_env("DST_SUBNETS").unwrap_or(defaults.dst_subnets),
+            dst_ports: parse_weighted_env("DST_PORT_WEIGHTS", |s| s.parse::<u16>().ok())
+                .unwrap_or(defaults.dst_ports),
+            protocols: parse_weighted_env("PROTOCOL_WEIGHTS", |s| s.parse::<u8>().ok())
+                .unwrap_or(defaults.protocols),
+            flows_per_packet: {
+                let min = env_var_parse("FLOWS_PER_PACKET_MIN").unwrap_or(defaults.flows_per_packet.0);
+                let max = env_var_parse("FLOWS_PER_PACKET_MAX").unwrap_or(defaults.flows_per_packet.1);
+                if min <= max {
+                    (min, max)
+                } else {
+                    defaults.flows_per_packet
+                }
+            },
+            sampling_interval: env_var_parse("SAMPLING_INTERVAL").unwrap_or(defaults.sampling_interval),
+        }
+    }
+}
+
+fn env_var_parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().and_then(|v| v.trim().parse().ok())
+}
+
+fn parse_subnets_env(key: &str) -> Option<Vec<(u8, u8, u8)>> {
+    let raw = env::var(key).ok()?;
+    let subnets: Vec<(u8, u8, u8)> = raw
+        .split(',')
+        .filter_map(|entry| {
+            let mut octets = entry.trim().splitn(3, '.');
+            let a = octets.next()?.parse().ok()?;
+            let b = octets.next()?.parse().ok()?;
+            let c = octets.next()?.parse().ok()?;
+            Some((a, b, c))
+        })
+        .collect();
+    if subnets.is_empty() {
+        None
+    } else {
+        Some(subnets)
+    }
+}
+
+// Returns `None` (falling back to the default list) if the env var is unset, parses to no
+// entries, or the weights sum to zero - a `WeightedIndex` built from a zero-total list panics, so
+// an operator typo like `DST_PORT_WEIGHTS=80:0` must be rejected here rather than reaching it.
+fn parse_weighted_env<T>(key: &str, parse_value: impl Fn(&str) -> Option<T>) -> Option<Vec<(T, u32)>> {
+    let raw = env::var(key).ok()?;
+    let weighted: Vec<(T, u32)> = raw
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.trim().splitn(2, ':');
+            let value = parse_value(parts.next()?)?;
+            let weight = parts.next()?.trim().parse().ok()?;
+            Some((value, weight))
+        })
+        .collect();
+    if weighted.is_empty() || weighted.iter().map(|(_, w)| *w).sum::<u32>() == 0 {
+        None
+    } else {
+        Some(weighted)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportTransport {
+    Udp,
+    Tcp,
+    Sctp,
+}
+
+impl ExportTransport {
+    fn from_env() -> Self {
+        match env::var("EXPORT_TRANSPORT")
+            .unwrap_or_else(|_| "udp".to_string())
+            .to_lowercase()
+            .as_str()
+        {
+            "tcp" => ExportTransport::Tcp,
+            "sctp" => ExportTransport::Sctp,
+            _ => ExportTransport::Udp,
+        }
+    }
+}
 
 #[derive(Debug)]
 struct NetFlowSender {
     target_host: String,
     target_port: u16,
+    transport: ExportTransport,
+    profile: TrafficProfile,
     sequence: u32,
 }
 
 impl NetFlowSender {
-    fn new(target_host: String, target_port: u16) -> Self {
+    fn new(target_host: String, target_port: u16, transport: ExportTransport, profile: TrafficProfile) -> Self {
         Self {
             target_host,
             target_port,
-            sequence: 1,
+            transport,
+            profile,
+            sequence: 0,
         }
     }
 
     fn create_netflow_packet(&mut self) -> Vec<u8> {
         let mut rng = rand::thread_rng();
 
-        let src_ip_str = format!("192.168.2.{}", rng.gen_range(1..=254));
-        let dst_ip_str = format!("10.0.1.{}", rng.gen_range(1..=254));
-        let src_port: u16 = rng.gen_range(1024..=65535);
-        let dst_ports = [80u16, 443, 22, 25, 53, 8080];
-        let dst_port = *dst_ports.choose(&mut rng).unwrap();
-        let protocols = [6u8, 17, 1];
-        let protocol = *protocols.choose(&mut rng).unwrap();
-        let packets: u32 = rng.gen_range(1..=100);
-        let bytes_count: u32 = packets * rng.gen_range(64..=1500);
+        let port_dist = WeightedIndex::new(self.profile.dst_ports.iter().map(|(_, w)| *w)).unwrap();
+        let protocol_dist = WeightedIndex::new(self.profile.protocols.iter().map(|(_, w)| *w)).unwrap();
+        let sampling_interval = self.profile.sampling_interval.max(1);
+
+        let flow_count = rng.gen_range(self.profile.flows_per_packet.0..=self.profile.flows_per_packet.1);
+
+        let mut flows = Vec::with_capacity(flow_count as usize * 48);
+        for _ in 0..flow_count {
+            let (a, b, c) = *self.profile.src_subnets.choose(&mut rng).unwrap();
+            let src_ip = Ipv4Addr::new(a, b, c, rng.gen_range(1..=254));
+            let (a, b, c) = *self.profile.dst_subnets.choose(&mut rng).unwrap();
+            let dst_ip = Ipv4Addr::new(a, b, c, rng.gen_range(1..=254));
+            let next_hop = Ipv4Addr::new(a, b, c, 1);
+
+            let src_port: u16 = rng.gen_range(1024..=65535);
+            let dst_port = self.profile.dst_ports[port_dist.sample(&mut rng)].0;
+            let protocol = self.profile.protocols[protocol_dist.sample(&mut rng)].0;
+
+            // Counts observed on the wire are scaled by the sampling interval so the exported
+            // record reflects the full, pre-sampling flow volume it represents.
+            let observed_packets: u32 = rng.gen_range(1..=100);
+            let observed_bytes: u32 = observed_packets * rng.gen_range(64..=1500);
+            let packets = observed_packets.saturating_mul(sampling_interval as u32);
+            let bytes_count = observed_bytes.saturating_mul(sampling_interval as u32);
+
+            let mut flow = vec![0u8; 48];
+            let mut wf = &mut flow[..];
+
+            wf.write_u32::<BigEndian>(u32::from_be_bytes(src_ip.octets())).unwrap();
+            wf.write_u32::<BigEndian>(u32::from_be_bytes(dst_ip.octets())).unwrap();
+            wf.write_u32::<BigEndian>(u32::from_be_bytes(next_hop.octets())).unwrap();
+            wf.write_u16::<BigEndian>(1).unwrap(); // input_snmp
+            wf.write_u16::<BigEndian>(2).unwrap(); // output_snmp
+            wf.write_u32::<BigEndian>(packets).unwrap(); // packets
+            wf.write_u32::<BigEndian>(bytes_count).unwrap(); // bytes
+            wf.write_u32::<BigEndian>(1000).unwrap(); // first
+            wf.write_u32::<BigEndian>(2000).unwrap(); // last
+            wf.write_u16::<BigEndian>(src_port).unwrap(); // srcport
+            wf.write_u16::<BigEndian>(dst_port).unwrap(); // dstport
+            wf.write_u8(0).unwrap(); // pad1
+            wf.write_u8(0x18).unwrap(); // tcp_flags
+            wf.write_u8(protocol).unwrap(); // protocol
+            wf.write_u8(0).unwrap(); // tos
+            wf.write_u16::<BigEndian>(65001).unwrap(); // src_as
+            wf.write_u16::<BigEndian>(65002).unwrap(); // dst_as
+            wf.write_u8(24).unwrap(); // src_mask
+            wf.write_u8(24).unwrap(); // dst_mask
+            wf.write_u16::<BigEndian>(0).unwrap(); // pad2
+
+            flows.extend_from_slice(&flow);
+        }
+
+        // flow_sequence is the sequence number of the first flow in this packet, per RFC 3954;
+        // it advances by flows exported, not packets sent, so a downstream collector can detect
+        // gaps even when packets carry a varying number of flows.
+        let starting_sequence = self.sequence;
+        self.sequence = self.sequence.wrapping_add(flow_count);
 
         // Header (24 bytes)
         let mut header = vec![0u8; 24];
         let mut w = &mut header[..];
         w.write_u16::<BigEndian>(5).unwrap(); // version
-        w.write_u16::<BigEndian>(1).unwrap(); // count
+        w.write_u16::<BigEndian>(flow_count as u16).unwrap(); // count: flows actually appended
         w.write_u32::<BigEndian>(rng.gen_range(10000..=99999)).unwrap(); // sys_uptime
         w.write_u32::<BigEndian>(Utc::now().timestamp() as u32).unwrap(); // unix_secs
         w.write_u32::<BigEndian>(0).unwrap(); // unix_nsecs
-        w.write_u32::<BigEndian>(self.sequence).unwrap(); // flow_sequence
+        w.write_u32::<BigEndian>(starting_sequence).unwrap(); // flow_sequence
         w.write_u8(0).unwrap(); // engine_type
         w.write_u8(0).unwrap(); // engine_id
-        w.write_u16::<BigEndian>(0).unwrap(); // sampling_interval
-
-        // Flow record (48 bytes)
-        let mut flow = vec![0u8; 48];
-        let mut wf = &mut flow[..];
-
-        // IPs as u32 in network byte order
-        let src_ip_bytes = src_ip_str.parse::<std::net::Ipv4Addr>().unwrap().octets();
-        wf.write_u32::<BigEndian>(u32::from_be_bytes(src_ip_bytes)).unwrap();
-        let dst_ip_bytes = dst_ip_str.parse::<std::net::Ipv4Addr>().unwrap().octets();
-        wf.write_u32::<BigEndian>(u32::from_be_bytes(dst_ip_bytes)).unwrap();
-        let next_hop_str = "192.168.2.1";
-        let next_hop_bytes = next_hop_str.parse::<std::net::Ipv4Addr>().unwrap().octets();
-        wf.write_u32::<BigEndian>(u32::from_be_bytes(next_hop_bytes)).unwrap();
-        wf.write_u16::<BigEndian>(1).unwrap(); // input_snmp
-        wf.write_u16::<BigEndian>(2).unwrap(); // output_snmp
-        wf.write_u32::<BigEndian>(packets).unwrap(); // packets
-        wf.write_u32::<BigEndian>(bytes_count).unwrap(); // bytes
-        wf.write_u32::<BigEndian>(1000).unwrap(); // first
-        wf.write_u32::<BigEndian>(2000).unwrap(); // last
-        wf.write_u16::<BigEndian>(src_port).unwrap(); // srcport
-        wf.write_u16::<BigEndian>(dst_port).unwrap(); // dstport
-        wf.write_u8(0).unwrap(); // pad1
-        wf.write_u8(0x18).unwrap(); // tcp_flags
-        wf.write_u8(protocol).unwrap(); // protocol
-        wf.write_u8(0).unwrap(); // tos
-        wf.write_u16::<BigEndian>(65001).unwrap(); // src_as
-        wf.write_u16::<BigEndian>(65002).unwrap(); // dst_as
-        wf.write_u8(24).unwrap(); // src_mask
-        wf.write_u8(24).unwrap(); // dst_mask
-        wf.write_u16::<BigEndian>(0).unwrap(); // pad2
-
-        self.sequence += 1;
+        w.write_u16::<BigEndian>(sampling_interval).unwrap(); // sampling_interval
 
         let mut packet = header;
-        packet.extend_from_slice(&flow);
+        packet.extend_from_slice(&flows);
         packet
     }
 
-    async fn send_packets(&mut self) {
-        println!(
-            "Starting netflow sender to {}:{}",
-            self.target_host, self.target_port
-        );
+    /// Resolves `target_host:target_port`, retrying forever every 5 seconds on failure. Shared
+    /// by every transport so DNS flakiness is handled in one place instead of per send loop.
+    async fn resolve_target(&self) -> SocketAddr {
+        loop {
+            let resolve_str = format!("{}:{}", self.target_host, self.target_port);
+            match lookup_host(&resolve_str).await {
+                Ok(mut addrs) => {
+                    if let Some(addr) = addrs.next() {
+                        return addr;
+                    }
+                    println!("No IP addresses found for {}", self.target_host);
+                }
+                Err(e) => {
+                    println!("DNS resolution error: {}", e);
+                }
+            }
+            telemetry::record_dns_retry();
+            println!("Retrying in 5 seconds...");
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    }
 
-        tokio::time::sleep(Duration::from_secs(5)).await;
+    /// Opens a stream connection to `addr` for the sender's configured transport. TCP gets
+    /// `set_nodelay(true)` so low-rate flow export isn't held back up to 200ms by Nagle's
+    /// algorithm. SCTP one-to-one sockets speak the same connect/read/write semantics as TCP at
+    /// the syscall level, so once connected the fd is handed to tokio's `TcpStream` for polling
+    /// rather than pulling in a separate async SCTP stack.
+    async fn connect_stream(&self, addr: SocketAddr) -> std::io::Result<TcpStream> {
+        match self.transport {
+            ExportTransport::Tcp => {
+                let stream = TcpStream::connect(addr).await?;
+                stream.set_nodelay(true)?;
+                Ok(stream)
+            }
+            ExportTransport::Sctp => {
+                let domain = if addr.is_ipv4() { Domain::IPV4 } else { Domain::IPV6 };
+                let socket = Socket::new(domain, Type::STREAM, Some(Protocol::from(IPPROTO_SCTP)))?;
+                socket.set_nonblocking(true)?;
+                match socket.connect(&addr.into()) {
+                    Ok(()) => {}
+                    Err(e)
+                        if e.kind() == std::io::ErrorKind::WouldBlock
+                            || e.raw_os_error() == Some(EINPROGRESS) => {}
+                    Err(e) => return Err(e),
+                }
+                let std_stream: std::net::TcpStream = socket.into();
+                let stream = TcpStream::from_std(std_stream)?;
+                stream.writable().await?;
+
+                // `writable()` only means the connect() syscall resolved, not that it succeeded -
+                // a refused or failed async connect still completes by becoming writable. Check
+                // the socket's pending error before treating it as connected, or a bad connection
+                // silently passes here and only surfaces on the first write_all.
+                if let Some(err) = stream.take_error()? {
+                    return Err(err);
+                }
 
+                Ok(stream)
+            }
+            ExportTransport::Udp => unreachable!("UDP does not use a stream connection"),
+        }
+    }
+
+    async fn send_packets_udp(&mut self) {
         let sock = match UdpSocket::bind("0.0.0.0:0").await {
             Ok(s) => s,
             Err(e) => {
@@ -107,61 +316,124 @@ impl NetFlowSender {
         let mut packet_count = 0;
 
         loop {
-            let resolve_str = format!("{}:{}", self.target_host, self.target_port);
-            let target_addr: SocketAddr = match lookup_host(&resolve_str).await {
-                Ok(mut addrs) => match addrs.next() {
-                    Some(addr) => addr,
-                    None => {
-                        println!("No IP addresses found for {}", self.target_host);
-                        println!("Retrying in 5 seconds...");
-                        tokio::time::sleep(Duration::from_secs(5)).await;
-                        continue;
-                    }
-                },
-                Err(e) => {
-                    println!("DNS resolution error: {}", e);
-                    println!("Retrying in 5 seconds...");
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                    continue;
-                }
-            };
-
+            let target_addr = self.resolve_target().await;
             let packet = self.create_netflow_packet();
 
             match sock.send_to(&packet, target_addr).await {
                 Ok(_) => {
                     packet_count += 1;
+                    telemetry::record_packet_sent();
                     println!(
                         "Sent packet {} to {}:{}",
                         packet_count, self.target_host, self.target_port
                     );
-                    println!("  Sequence: {}", self.sequence - 1);
+                    println!("  Flow sequence now: {}", self.sequence);
                     println!("  Size: {} bytes", packet.len());
                 }
                 Err(e) => {
                     println!("Error sending packet: {}", e);
+                    telemetry::record_send_error();
                     tokio::time::sleep(Duration::from_secs(2)).await;
                     continue;
                 }
             }
 
             let sleep_sec = rand::thread_rng().gen_range(1..=5);
+            telemetry::record_inter_packet_sleep(sleep_sec as f64);
             tokio::time::sleep(Duration::from_secs(sleep_sec)).await;
         }
     }
+
+    /// Drives the TCP/SCTP export path: each NetFlow PDU is length-prefixed (u32 BE byte count)
+    /// so the collector can frame PDUs off the stream, and a dropped connection is reconnected
+    /// with exponential backoff instead of ending the sender.
+    async fn send_packets_stream(&mut self) {
+        let mut packet_count: u64 = 0;
+        let mut backoff = Duration::from_secs(1);
+
+        loop {
+            let target_addr = self.resolve_target().await;
+
+            let mut stream = match self.connect_stream(target_addr).await {
+                Ok(s) => {
+                    backoff = Duration::from_secs(1);
+                    s
+                }
+                Err(e) => {
+                    println!(
+                        "Error connecting via {:?} to {}: {}",
+                        self.transport, target_addr, e
+                    );
+                    println!("Reconnecting in {:?}...", backoff);
+                    tokio::time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+                    continue;
+                }
+            };
+
+            println!("Connected to {} via {:?}", target_addr, self.transport);
+
+            loop {
+                let packet = self.create_netflow_packet();
+                let mut framed = Vec::with_capacity(4 + packet.len());
+                framed.write_u32::<BigEndian>(packet.len() as u32).unwrap();
+                framed.extend_from_slice(&packet);
+
+                if let Err(e) = stream.write_all(&framed).await {
+                    println!("Error sending packet over {:?}: {}", self.transport, e);
+                    telemetry::record_send_error();
+                    println!("Connection dropped, reconnecting in {:?}...", backoff);
+                    tokio::time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+                    break;
+                }
+
+                packet_count += 1;
+                telemetry::record_packet_sent();
+                println!(
+                    "Sent packet {} to {}:{} ({:?})",
+                    packet_count, self.target_host, self.target_port, self.transport
+                );
+                println!("  Flow sequence now: {}", self.sequence);
+                println!("  Size: {} bytes", packet.len());
+
+                let sleep_sec = rand::thread_rng().gen_range(1..=5);
+                telemetry::record_inter_packet_sleep(sleep_sec as f64);
+                tokio::time::sleep(Duration::from_secs(sleep_sec)).await;
+            }
+        }
+    }
+
+    async fn send_packets(&mut self) {
+        println!(
+            "Starting netflow sender to {}:{} via {:?}",
+            self.target_host, self.target_port, self.transport
+        );
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        match self.transport {
+            ExportTransport::Udp => self.send_packets_udp().await,
+            ExportTransport::Tcp | ExportTransport::Sctp => self.send_packets_stream().await,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     println!("=== Netflow Sender Container ===");
 
+    telemetry::init();
+
     let target_host = env::var("RECEIVER_HOST").unwrap_or_else(|_| "receiver".to_string());
     let target_port_str = env::var("RECEIVER_PORT").unwrap_or_else(|_| "2055".to_string());
     let target_port: u16 = target_port_str.parse()?;
+    let transport = ExportTransport::from_env();
+    let profile = TrafficProfile::from_env();
 
-    let mut sender = NetFlowSender::new(target_host, target_port);
+    let mut sender = NetFlowSender::new(target_host, target_port, transport, profile);
 
     sender.send_packets().await;
 
     Ok(())
-}
\ No newline at end of file
+}
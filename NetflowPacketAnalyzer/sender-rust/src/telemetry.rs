@@ -0,0 +1,78 @@
+// sender-rust/src/telemetry.rs
+//
+// Sender-side instrumentation, gated behind the `telemetry` feature so a default build has zero
+// runtime cost. When the feature is enabled, counters and a sleep-duration histogram are
+// exported via OTLP so operators can see export throughput and error ratios alongside the
+// parser's metrics.
+
+#[cfg(feature = "telemetry")]
+mod imp {
+    use once_cell::sync::Lazy;
+    use opentelemetry::metrics::{Counter, Histogram, Meter};
+    use opentelemetry::global;
+    use opentelemetry_otlp::WithExportConfig;
+
+    static METER: Lazy<Meter> = Lazy::new(|| global::meter("netflow_sender"));
+
+    static PACKETS_SENT: Lazy<Counter<u64>> = Lazy::new(|| {
+        METER
+            .u64_counter("netflow.sender.packets_sent")
+            .with_description("PDUs successfully sent")
+            .init()
+    });
+
+    static SEND_ERRORS: Lazy<Counter<u64>> = Lazy::new(|| {
+        METER
+            .u64_counter("netflow.sender.send_errors")
+            .with_description("Errors while sending a PDU")
+            .init()
+    });
+
+    static DNS_RETRIES: Lazy<Counter<u64>> = Lazy::new(|| {
+        METER
+            .u64_counter("netflow.sender.dns_retries")
+            .with_description("DNS resolution attempts that failed and were retried")
+            .init()
+    });
+
+    static INTER_PACKET_SLEEP: Lazy<Histogram<f64>> = Lazy::new(|| {
+        METER
+            .f64_histogram("netflow.sender.inter_packet_sleep_seconds")
+            .with_description("Sleep duration between sent PDUs")
+            .init()
+    });
+
+    pub fn init() {
+        let _ = opentelemetry_otlp::new_pipeline()
+            .metrics(opentelemetry::runtime::Tokio)
+            .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+            .build();
+    }
+
+    pub fn record_packet_sent() {
+        PACKETS_SENT.add(1, &[]);
+    }
+
+    pub fn record_send_error() {
+        SEND_ERRORS.add(1, &[]);
+    }
+
+    pub fn record_dns_retry() {
+        DNS_RETRIES.add(1, &[]);
+    }
+
+    pub fn record_inter_packet_sleep(seconds: f64) {
+        INTER_PACKET_SLEEP.record(seconds, &[]);
+    }
+}
+
+#[cfg(not(feature = "telemetry"))]
+mod imp {
+    pub fn init() {}
+    pub fn record_packet_sent() {}
+    pub fn record_send_error() {}
+    pub fn record_dns_retry() {}
+    pub fn record_inter_packet_sleep(_seconds: f64) {}
+}
+
+pub use imp::*;